@@ -1,6 +1,17 @@
 use std::collections::HashSet;
 use std::fmt::Debug;
 use std::mem;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "stream")]
+mod stream;
+#[cfg(feature = "stream")]
+pub use stream::StreamBatcher;
+
+#[cfg(feature = "service")]
+mod service;
+#[cfg(feature = "service")]
+pub use service::BatchingService;
 
 /// Element to batch together
 ///
@@ -9,6 +20,11 @@ pub trait Unit: Debug + Clone + Send + Sync {
     type ID: Debug + Eq + std::hash::Hash + Clone + Send + Sync;
     /// Returns the ID associated with this unit.
     fn id(&self) -> Self::ID;
+    /// Returns this unit's cost for `PolicyKind::ByWeight`, e.g. its serialized byte size.
+    /// Defaults to 1, i.e. weight degenerates to a unit count.
+    fn weight(&self) -> u64 {
+        1
+    }
 }
 
 /// Batcher is the main trait driving the creation of a batch.
@@ -29,9 +45,26 @@ pub trait Batcher {
 
 /// An enum implementing various batching strategies. User can implement its own strategy by
 /// implementing the `Policy` trait.
+#[derive(Clone)]
 pub enum PolicyKind<ID> {
     BySize(usize),
     ByList(HashSet<ID>),
+    /// Releases the batch once its oldest pending unit has been pending for at least the given
+    /// duration. The elapsed time is only checked when `new_unit` is called, so a batch is never
+    /// released by this policy while no new units arrive. [`StreamBatcher`] has its own timer,
+    /// which releases the pending batch independently of this policy's duration.
+    ByTimeout(Duration),
+    /// Releases once every child policy would release, e.g. "at least N items AND at least M
+    /// weight". An empty vector is degenerate and releases after every insertion (vacuously
+    /// true), so each batch holds a single unit; always pass at least one child.
+    All(Vec<PolicyKind<ID>>),
+    /// Releases as soon as any child policy would release, e.g. "N items OR M bytes". An empty
+    /// vector is degenerate and never releases (vacuously false), so always pass at least one
+    /// child.
+    Any(Vec<PolicyKind<ID>>),
+    /// Releases once the accumulated `Unit::weight` of the pending batch reaches `max`, e.g. to
+    /// bound total serialized bytes instead of just unit count.
+    ByWeight(u64),
 }
 
 /// Policy implementing the batcher trait.
@@ -53,18 +86,32 @@ enum BatchStatus {
 #[derive(Default)]
 struct VecBatcher<U> {
     pending: Vec<U>,
+    /// When the first unit of the current pending batch was inserted, used by
+    /// `PolicyKind::ByTimeout`.
+    first_inserted: Option<Instant>,
+    /// Running sum of `Unit::weight` over `pending`, kept up to date incrementally so
+    /// `PolicyKind::ByWeight` doesn't need to re-sum the batch on every insertion.
+    current_weight: u64,
 }
 
 impl<U: Unit> VecBatcher<U> {
     pub fn new() -> Self {
         Self {
             pending: Default::default(),
+            first_inserted: None,
+            current_weight: 0,
         }
     }
     fn new_unit(&mut self, unit: U) {
+        if self.pending.is_empty() {
+            self.first_inserted = Some(Instant::now());
+        }
+        self.current_weight += unit.weight();
         self.pending.push(unit);
     }
     fn release(&mut self) -> Vec<U> {
+        self.first_inserted = None;
+        self.current_weight = 0;
         mem::take(&mut self.pending)
     }
 }
@@ -93,6 +140,34 @@ where
                     .collect::<HashSet<ID>>()
                     == set,
             ),
+            PolicyKind::ByTimeout(max) => BatchStatus::from(
+                batch
+                    .first_inserted
+                    .is_some_and(|inserted| inserted.elapsed() >= *max),
+            ),
+            PolicyKind::All(children) => {
+                debug_assert!(
+                    !children.is_empty(),
+                    "PolicyKind::All([]) vacuously releases every batch; pass at least one child"
+                );
+                BatchStatus::from(
+                    children
+                        .iter()
+                        .all(|child| matches!(child.outcome(batch), BatchStatus::ReleaseBatch)),
+                )
+            }
+            PolicyKind::Any(children) => {
+                debug_assert!(
+                    !children.is_empty(),
+                    "PolicyKind::Any([]) never releases; pass at least one child"
+                );
+                BatchStatus::from(
+                    children
+                        .iter()
+                        .any(|child| matches!(child.outcome(batch), BatchStatus::ReleaseBatch)),
+                )
+            }
+            PolicyKind::ByWeight(max) => BatchStatus::from(batch.current_weight >= *max),
         }
     }
 }
@@ -124,6 +199,124 @@ where
     }
 }
 
+/// Like `PolicyBatcher`, but `new_unit` drops units whose ID is already present in the pending
+/// batch instead of inserting a duplicate.
+///
+/// By default the first unit with a given ID wins and later duplicates are dropped; construct
+/// with `with_keep_last` to instead replace the pending unit with the latest duplicate.
+pub struct DedupBatcher<U: Unit> {
+    backend: VecBatcher<U>,
+    policy: PolicyKind<U::ID>,
+    seen: HashSet<U::ID>,
+    keep_last: bool,
+}
+
+impl<U: Unit> DedupBatcher<U> {
+    pub fn new(policy: PolicyKind<U::ID>) -> Self {
+        Self {
+            backend: VecBatcher::new(),
+            policy,
+            seen: HashSet::new(),
+            keep_last: false,
+        }
+    }
+
+    pub fn with_keep_last(policy: PolicyKind<U::ID>) -> Self {
+        Self {
+            backend: VecBatcher::new(),
+            policy,
+            seen: HashSet::new(),
+            keep_last: true,
+        }
+    }
+}
+
+impl<U> Batcher for DedupBatcher<U>
+where
+    U: Unit,
+{
+    type Unit = U;
+
+    fn new_unit(&mut self, unit: Self::Unit) -> Option<Vec<Self::Unit>> {
+        let id = unit.id();
+        if self.seen.contains(&id) {
+            if self.keep_last {
+                if let Some(pos) = self.backend.pending.iter().position(|u| u.id() == id) {
+                    let old = self.backend.pending.remove(pos);
+                    self.backend.current_weight -= old.weight();
+                }
+                self.backend.new_unit(unit);
+            }
+            // else: drop the duplicate, keeping the first unit with this ID.
+        } else {
+            self.seen.insert(id);
+            self.backend.new_unit(unit);
+        }
+        match self.policy.outcome(&self.backend) {
+            BatchStatus::KeepBatching => None,
+            BatchStatus::ReleaseBatch => {
+                self.seen.clear();
+                Some(self.backend.release())
+            }
+        }
+    }
+
+    fn release(mut self) -> Vec<Self::Unit> {
+        self.backend.release()
+    }
+}
+
+/// Routes units into independent sub-batches keyed by `extract_key`, each sub-batch driven by its
+/// own clone of the same `PolicyKind`.
+///
+/// Unlike `PolicyBatcher`, which forces every unit into a single pending batch, this lets callers
+/// keep e.g. per-destination streams batched separately.
+pub struct PartitionedBatcher<U, K, F>
+where
+    U: Unit,
+    K: Eq + std::hash::Hash + Clone,
+    F: Fn(&U) -> K,
+{
+    partitions: std::collections::HashMap<K, PolicyBatcher<U>>,
+    policy: PolicyKind<U::ID>,
+    extract_key: F,
+}
+
+impl<U, K, F> PartitionedBatcher<U, K, F>
+where
+    U: Unit,
+    K: Eq + std::hash::Hash + Clone,
+    F: Fn(&U) -> K,
+{
+    pub fn new(policy: PolicyKind<U::ID>, extract_key: F) -> Self {
+        Self {
+            partitions: std::collections::HashMap::new(),
+            policy,
+            extract_key,
+        }
+    }
+
+    /// Inserts `unit` into the partition for its key, returning that partition's key and batch
+    /// only if inserting `unit` just released it. Other partitions are left untouched.
+    pub fn new_unit(&mut self, unit: U) -> Option<(K, Vec<U>)> {
+        let key = (self.extract_key)(&unit);
+        let policy = self.policy.clone();
+        let partition = self
+            .partitions
+            .entry(key.clone())
+            .or_insert_with(|| PolicyBatcher::new(policy));
+        partition.new_unit(unit).map(|batch| (key, batch))
+    }
+
+    /// Releases every partition, regardless of whether its policy considers it ready.
+    pub fn release(self) -> Vec<(K, Vec<U>)> {
+        self.partitions
+            .into_iter()
+            .map(|(key, partition)| (key, partition.release()))
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -194,4 +387,137 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_by_timeout_policy() {
+        let mut batcher = PolicyBatcher::new(PolicyKind::ByTimeout(Duration::from_millis(20)));
+        assert_eq!(batcher.new_unit(TestUnit(1)), None);
+        // Re-inserting before the timeout elapses must not release.
+        assert_eq!(batcher.new_unit(TestUnit(2)), None);
+        std::thread::sleep(Duration::from_millis(30));
+        // ByTimeout only re-checks elapsed time on the next `new_unit` call.
+        assert_eq!(
+            batcher.new_unit(TestUnit(3)),
+            Some(vec![TestUnit(1), TestUnit(2), TestUnit(3)])
+        );
+    }
+
+    #[test]
+    fn test_partitioned_batcher_routes_by_key() {
+        let mut batcher = PartitionedBatcher::new(PolicyKind::BySize(2), |unit: &TestUnit| unit.0 % 2);
+        // Partition 1 (odd) isn't ready yet.
+        assert_eq!(batcher.new_unit(TestUnit(1)), None);
+        // Partition 0 (even) isn't ready yet either, and is independent from partition 1.
+        assert_eq!(batcher.new_unit(TestUnit(2)), None);
+        // Completing partition 1 doesn't disturb partition 0's pending unit.
+        assert_eq!(
+            batcher.new_unit(TestUnit(3)),
+            Some((1, vec![TestUnit(1), TestUnit(3)]))
+        );
+        // Partition 0 now also reaches BySize(2) and releases on its own.
+        assert_eq!(
+            batcher.new_unit(TestUnit(4)),
+            Some((0, vec![TestUnit(2), TestUnit(4)]))
+        );
+        // Both partitions were already flushed above, so releasing now yields nothing pending.
+        let mut remaining = batcher.release();
+        remaining.sort_by_key(|(key, _)| *key);
+        assert_eq!(remaining, vec![(0, vec![]), (1, vec![])]);
+    }
+
+    #[test]
+    fn test_all_releases_only_once_every_child_does() {
+        // BySize(3) alone wouldn't release until the 3rd unit; ByList only matches the exact
+        // pending id-set {1, 2, 3}, which is also only true once the 3rd unit is in. Both
+        // children become true together, so All releases then and not before.
+        let mut batcher = PolicyBatcher::new(PolicyKind::All(vec![
+            PolicyKind::BySize(3),
+            PolicyKind::ByList(HashSet::from([1, 2, 3])),
+        ]));
+        assert_eq!(batcher.new_unit(TestUnit(1)), None);
+        assert_eq!(batcher.new_unit(TestUnit(2)), None);
+        assert_eq!(
+            batcher.new_unit(TestUnit(3)),
+            Some(vec![TestUnit(1), TestUnit(2), TestUnit(3)])
+        );
+    }
+
+    #[test]
+    fn test_any_releases_as_soon_as_one_child_does() {
+        let mut batcher = PolicyBatcher::new(PolicyKind::Any(vec![
+            PolicyKind::BySize(100),
+            PolicyKind::ByList(HashSet::from([1, 2])),
+        ]));
+        assert_eq!(batcher.new_unit(TestUnit(1)), None);
+        assert_eq!(
+            batcher.new_unit(TestUnit(2)),
+            Some(vec![TestUnit(1), TestUnit(2)])
+        );
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct WeightedUnit {
+        id: usize,
+        cost: u64,
+    }
+    impl Unit for WeightedUnit {
+        type ID = usize;
+
+        fn id(&self) -> Self::ID {
+            self.id
+        }
+        fn weight(&self) -> u64 {
+            self.cost
+        }
+    }
+
+    #[test]
+    fn test_by_weight_policy_tracks_running_weight() {
+        let mut batcher = PolicyBatcher::new(PolicyKind::ByWeight(10));
+        assert_eq!(
+            batcher.new_unit(WeightedUnit { id: 1, cost: 4 }),
+            None
+        );
+        assert_eq!(
+            batcher.new_unit(WeightedUnit { id: 2, cost: 5 }),
+            None
+        );
+        assert_eq!(
+            batcher.new_unit(WeightedUnit { id: 3, cost: 1 }),
+            Some(vec![
+                WeightedUnit { id: 1, cost: 4 },
+                WeightedUnit { id: 2, cost: 5 },
+                WeightedUnit { id: 3, cost: 1 },
+            ])
+        );
+        // The running weight resets after release.
+        assert_eq!(batcher.new_unit(WeightedUnit { id: 4, cost: 9 }), None);
+    }
+
+    #[test]
+    fn test_dedup_batcher_drops_duplicates_by_default() {
+        let mut batcher = DedupBatcher::new(PolicyKind::BySize(2));
+        assert_eq!(batcher.new_unit(TestUnit(1)), None);
+        // Same ID again: dropped, first one is kept, still not ready.
+        assert_eq!(batcher.new_unit(TestUnit(1)), None);
+        assert_eq!(
+            batcher.new_unit(TestUnit(2)),
+            Some(vec![TestUnit(1), TestUnit(2)])
+        );
+    }
+
+    #[test]
+    fn test_dedup_batcher_keep_last_replaces_and_adjusts_weight() {
+        let mut batcher = DedupBatcher::with_keep_last(PolicyKind::ByWeight(10));
+        assert_eq!(
+            batcher.new_unit(WeightedUnit { id: 1, cost: 4 }),
+            None
+        );
+        // Same ID with a higher cost: replaces the pending unit and its weight contribution,
+        // pushing the running weight over the threshold.
+        assert_eq!(
+            batcher.new_unit(WeightedUnit { id: 1, cost: 12 }),
+            Some(vec![WeightedUnit { id: 1, cost: 12 }])
+        );
+    }
 }
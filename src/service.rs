@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::future::Future;
+
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{Batcher, Unit};
+
+/// An async batching queue: producers submit units and await a handle that resolves once their
+/// unit's batch has been released and processed.
+///
+/// A background task owns the `Batcher`, draining submissions and, every time a batch releases,
+/// calling the handler and fanning its result back out to each waiting submitter. Because the
+/// channel is buffered, producers can keep enqueueing while a prior batch is still being handled.
+///
+/// If multiple pending submissions share the same `Unit::ID`, they are not distinguishable by the
+/// handler's per-ID result map, so all of them resolve to a clone of that same result rather than
+/// silently dropping all but the most recent submitter.
+pub struct BatchingService<U: Unit, R> {
+    sender: mpsc::Sender<(U, oneshot::Sender<R>)>,
+}
+
+impl<U, R> BatchingService<U, R>
+where
+    U: Unit + 'static,
+    R: Clone + Send + 'static,
+{
+    /// Spawns the background task. `handler` is called with each released batch and must return
+    /// a result per unit, keyed by `Unit::ID`; submitters of units whose ID is missing from the
+    /// result get `None`.
+    pub fn spawn<B, H, Fut>(mut batcher: B, queue_size: usize, handler: H) -> Self
+    where
+        B: Batcher<Unit = U> + Send + 'static,
+        H: Fn(Vec<U>) -> Fut + Send + 'static,
+        Fut: Future<Output = HashMap<U::ID, R>> + Send,
+    {
+        let (sender, mut receiver) = mpsc::channel::<(U, oneshot::Sender<R>)>(queue_size);
+        tokio::spawn(async move {
+            let mut waiting: HashMap<U::ID, Vec<oneshot::Sender<R>>> = HashMap::new();
+            while let Some((unit, respond_to)) = receiver.recv().await {
+                waiting.entry(unit.id()).or_default().push(respond_to);
+                if let Some(batch) = batcher.new_unit(unit) {
+                    let mut results = handler(batch).await;
+                    for (id, respond_tos) in waiting.drain() {
+                        if let Some(result) = results.remove(&id) {
+                            for respond_to in respond_tos {
+                                let _ = respond_to.send(result.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        });
+        Self { sender }
+    }
+
+    /// Submits a unit and waits for its batch to be released and processed. Returns `None` if the
+    /// handler's result has no entry for this unit's ID, or if the background task stopped
+    /// before resolving this unit.
+    pub async fn submit(&self, unit: U) -> Option<R> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender.send((unit, respond_to)).await.ok()?;
+        response.await.ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PolicyBatcher;
+    use crate::PolicyKind;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct TestUnit(usize);
+    impl Unit for TestUnit {
+        type ID = usize;
+
+        fn id(&self) -> Self::ID {
+            self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn fans_the_handler_result_out_to_each_submitter() {
+        let service = BatchingService::spawn(
+            PolicyBatcher::new(PolicyKind::BySize(2)),
+            8,
+            |batch: Vec<TestUnit>| async move {
+                batch
+                    .into_iter()
+                    .map(|unit| (unit.id(), unit.id() * 10))
+                    .collect::<HashMap<_, _>>()
+            },
+        );
+
+        let (a_result, b_result) = tokio::join!(service.submit(TestUnit(1)), service.submit(TestUnit(2)));
+        assert_eq!(a_result, Some(10));
+        assert_eq!(b_result, Some(20));
+    }
+
+    #[tokio::test]
+    async fn duplicate_ids_all_resolve_to_the_same_result() {
+        let service = BatchingService::spawn(
+            PolicyBatcher::new(PolicyKind::BySize(2)),
+            8,
+            |batch: Vec<TestUnit>| async move {
+                batch
+                    .into_iter()
+                    .map(|unit| (unit.id(), unit.id() * 10))
+                    .collect::<HashMap<_, _>>()
+            },
+        );
+
+        let first = service.submit(TestUnit(1));
+        let second = service.submit(TestUnit(1));
+        let (first_result, second_result) = tokio::join!(first, second);
+        assert_eq!(first_result, Some(10));
+        assert_eq!(second_result, Some(10));
+    }
+}
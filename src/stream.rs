@@ -0,0 +1,156 @@
+use std::pin::Pin;
+use std::time::Duration;
+
+use futures::{Stream, StreamExt};
+use tokio::time::{self, Sleep};
+
+use crate::Batcher;
+
+/// Drives a `Batcher` from a `futures::Stream` of units, adding a debounce timeout on top of
+/// whatever release policy the inner batcher already implements.
+///
+/// The first unit of a new pending batch arms a timer; the batch is released either when the
+/// inner batcher's policy fires or when the timer elapses, whichever happens first. The timer is
+/// disarmed after every release so it never fires against an empty batch.
+pub struct StreamBatcher<F, B, S>
+where
+    F: FnMut() -> B,
+    B: Batcher,
+    S: Stream<Item = B::Unit> + Unpin,
+{
+    make_batcher: F,
+    batcher: B,
+    stream: S,
+    timeout: Duration,
+    deadline: Option<Pin<Box<Sleep>>>,
+}
+
+impl<F, B, S> StreamBatcher<F, B, S>
+where
+    F: FnMut() -> B,
+    B: Batcher,
+    S: Stream<Item = B::Unit> + Unpin,
+{
+    /// `make_batcher` is called once up front and again every time the wrapper releases a batch
+    /// itself (on timeout or when the stream ends), since `Batcher::release` consumes the
+    /// batcher. A batch released by the inner policy keeps using the same batcher.
+    pub fn new(mut make_batcher: F, stream: S, timeout: Duration) -> Self {
+        let batcher = make_batcher();
+        Self {
+            make_batcher,
+            batcher,
+            stream,
+            timeout,
+            deadline: None,
+        }
+    }
+
+    /// Waits for the next batch to be released, combining the inner policy with the debounce
+    /// timeout. Returns `None` once the stream is exhausted and fully drained.
+    ///
+    /// No `biased` ordering: under sustained stream traffic both arms stay fair, so the timeout
+    /// still gets a chance to fire instead of being starved by a constantly-ready stream.
+    pub async fn next_batch(&mut self) -> Option<Vec<B::Unit>> {
+        loop {
+            tokio::select! {
+                maybe_unit = self.stream.next() => {
+                    match maybe_unit {
+                        Some(unit) => {
+                            if self.deadline.is_none() {
+                                self.deadline = Some(Box::pin(time::sleep(self.timeout)));
+                            }
+                            if let Some(batch) = self.batcher.new_unit(unit) {
+                                self.deadline = None;
+                                return Some(batch);
+                            }
+                        }
+                        None => {
+                            // Stream exhausted: flush whatever is still pending rather than
+                            // dropping it silently.
+                            self.deadline = None;
+                            let batcher = std::mem::replace(&mut self.batcher, (self.make_batcher)());
+                            let batch = batcher.release();
+                            return if batch.is_empty() { None } else { Some(batch) };
+                        }
+                    }
+                }
+                _ = Self::wait_deadline(&mut self.deadline) => {
+                    self.deadline = None;
+                    let batcher = std::mem::replace(&mut self.batcher, (self.make_batcher)());
+                    let batch = batcher.release();
+                    if !batch.is_empty() {
+                        return Some(batch);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Awaits the armed timer, or never resolves while no timer is armed.
+    async fn wait_deadline(deadline: &mut Option<Pin<Box<Sleep>>>) {
+        match deadline {
+            Some(sleep) => sleep.await,
+            None => std::future::pending().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PolicyBatcher, PolicyKind, Unit};
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct TestUnit(usize);
+    impl Unit for TestUnit {
+        type ID = usize;
+
+        fn id(&self) -> Self::ID {
+            self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn releases_as_soon_as_the_inner_policy_is_satisfied() {
+        let stream = futures::stream::iter(vec![TestUnit(1), TestUnit(2)]).chain(futures::stream::pending());
+        let mut batcher = StreamBatcher::new(
+            || PolicyBatcher::new(PolicyKind::BySize(2)),
+            stream,
+            Duration::from_secs(60),
+        );
+        assert_eq!(
+            batcher.next_batch().await,
+            Some(vec![TestUnit(1), TestUnit(2)])
+        );
+    }
+
+    #[tokio::test]
+    async fn flushes_pending_batch_when_stream_ends() {
+        let stream = futures::stream::iter(vec![TestUnit(1), TestUnit(2)]);
+        let mut batcher = StreamBatcher::new(
+            || PolicyBatcher::new(PolicyKind::BySize(100)),
+            stream,
+            Duration::from_secs(60),
+        );
+        assert_eq!(
+            batcher.next_batch().await,
+            Some(vec![TestUnit(1), TestUnit(2)])
+        );
+        // The pending batch was already flushed; nothing left to emit.
+        assert_eq!(batcher.next_batch().await, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn releases_on_timeout_even_if_the_policy_never_fires() {
+        let stream = futures::stream::iter(vec![TestUnit(1)]).chain(futures::stream::pending());
+        let mut batcher = StreamBatcher::new(
+            || PolicyBatcher::new(PolicyKind::BySize(100)),
+            stream,
+            Duration::from_millis(50),
+        );
+        let next = tokio::spawn(async move { (batcher.next_batch().await, batcher) });
+        time::advance(Duration::from_millis(60)).await;
+        let (batch, _batcher) = next.await.unwrap();
+        assert_eq!(batch, Some(vec![TestUnit(1)]));
+    }
+}